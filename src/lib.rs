@@ -0,0 +1,845 @@
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+
+/// Pairs a node with its priority in the A* frontier.
+///
+/// Selection is based on the `cost` field (with a min heap), so the
+/// cheapest node is always expanded next. `cost` is the estimated total
+/// cost through the node, i.e. the cost travelled so far plus the
+/// heuristic estimate to the goal, kept as an `f64` so fractional
+/// (Euclidean) priorities are ordered exactly instead of being truncated.
+#[derive(Debug, Copy, Clone)]
+struct NodeCost<N> {
+    /// Estimated total cost through the node (travelled + heuristic)
+    cost: f64,
+    /// The node associated with the cost above
+    node: N,
+}
+
+// We compare `cost` with `f64::total_cmp` so that ordering is total even in
+// the presence of NaN, which `BinaryHeap` requires from its `Ord` bound.
+impl<N: Eq> PartialEq for NodeCost<N> {
+    fn eq(&self, other: &NodeCost<N>) -> bool {
+        self.cost.total_cmp(&other.cost) == Ordering::Equal && self.node == other.node
+    }
+}
+
+impl<N: Eq> Eq for NodeCost<N> {}
+
+// Got this idea from the example in std::collections::binary_heap
+// The priority queue depends on `Ord`.
+// Explicitly implement the trait so the queue becomes a min-heap
+// instead of a max-heap.
+impl<N: Ord> Ord for NodeCost<N> {
+    fn cmp(&self, other: &NodeCost<N>) -> Ordering {
+        // Notice that the we flip the ordering on costs.
+        // In case of a tie we compare the node - this step is necessary
+        // to make implementations of `PartialEq` and `Ord` consistent.
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| self.node.cmp(&other.node))
+    }
+}
+
+impl<N: Ord> PartialOrd for NodeCost<N> {
+    fn partial_cmp(&self, other: &NodeCost<N>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Runs a generic A* search from `start` and returns the cheapest path to
+/// the first node for which `success` holds, together with its travelled
+/// cost. Returns `None` if no such node is reachable.
+///
+/// The search is decoupled from any particular map representation: callers
+/// describe their graph with three closures, so the same routine drives
+/// grids, weighted graphs or game maps alike.
+///
+/// # Arguments
+///
+/// * `start` - The node the search starts from
+/// * `neighbours` - Yields the successors of a node together with the cost
+///   of the edge leading to each of them
+/// * `heuristic` - Estimates the remaining cost from a node to the goal
+/// * `success` - Decides whether a node is an acceptable goal. Using a
+///   predicate instead of a fixed index lets callers search for dynamic goals
+/// * `beam_width` - When `Some(w)`, only the `w` most promising frontier
+///   entries are kept after each expansion, bounding memory on huge graphs. This
+///   trades away admissibility, so with a finite beam the returned path is
+///   approximate; pass `None` for the exact search.
+pub fn astar<N, FN, FH, FS>(
+    start: N,
+    mut neighbours: FN,
+    mut heuristic: FH,
+    mut success: FS,
+    beam_width: Option<usize>,
+) -> Option<(Vec<N>, f64)>
+where
+    N: Copy + Eq + Hash + Ord,
+    FN: FnMut(&N) -> Vec<(N, f64)>,
+    FH: FnMut(&N) -> f64,
+    FS: FnMut(&N) -> bool,
+{
+    // This is a binary heap of many NodeCost structs ordered by the
+    // cost field.
+    // The containing nodes are considered to be explored next.
+    let mut frontier = BinaryHeap::new();
+    // Add the starting node with cost 0
+    frontier.push(NodeCost {
+        cost: 0_f64,
+        node: start,
+    });
+
+    // Contains the actual (travelled) cost (value) to a node (key)
+    let mut travelled_cost: HashMap<N, f64> = HashMap::new();
+    // Insert the start node with cost 0 as value
+    travelled_cost.insert(start, 0_f64);
+
+    // prev_node contains the previous node of a node.
+    // So the value of a key is the previous node of the node
+    // used for the key.
+    // Used to reconstruct the path through the graph in the end.
+    let mut prev_node: HashMap<N, N> = HashMap::new();
+
+    while let Some(current) = frontier.pop() {
+        // There is no decrease-key, so a node can sit in the frontier several
+        // times with different priorities. Lazily drop any entry whose stored
+        // priority is worse than the best one we have recorded for it by now -
+        // a cheaper path has since superseded it, so treat it as dead.
+        if current.cost > travelled_cost[&current.node] + heuristic(&current.node) {
+            continue;
+        }
+
+        // goal test
+        if success(&current.node) {
+            return Some((
+                reconstruct_path(&prev_node, current.node),
+                travelled_cost[&current.node],
+            ));
+        }
+
+        for (neighbour, step_cost) in neighbours(&current.node) {
+            // calculate cost from current node to selected neighbour node
+            let temp_cost: f64 = travelled_cost[&current.node] + step_cost;
+
+            // check if neighbour was already visited.
+            // If yes, there is a travelled cost in the hashmap travelled_cost
+            let cheaper = match travelled_cost.get(&neighbour) {
+                // If true, we found a cheaper path to neighbour
+                Some(cost) => temp_cost < *cost,
+                None => true,
+            };
+
+            if cheaper {
+                // Set the current node as previous node for the neighbour
+                prev_node.insert(neighbour, current.node);
+                // Set the travelled cost to neighbour over current
+                travelled_cost.insert(neighbour, temp_cost);
+
+                // Add travelled distance and heuristic
+                frontier.push(NodeCost {
+                    cost: temp_cost + heuristic(&neighbour),
+                    node: neighbour,
+                });
+            }
+        }
+
+        // With a beam, prune the frontier to its `beam_width` cheapest entries
+        // after each expansion: drain the heap into a vector, order it by
+        // priority, drop everything past the beam and rebuild the heap.
+        if let Some(width) = beam_width {
+            if frontier.len() > width {
+                let mut entries: Vec<NodeCost<N>> = frontier.drain().collect();
+                entries.sort_by(|a, b| a.cost.total_cmp(&b.cost));
+                entries.truncate(width);
+                frontier = entries.into_iter().collect();
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns a vector with the path from the start node to `current`.
+///
+/// # Arguments
+///
+/// * `prev_node` - A reference to the prev_node hashmap to find the
+///   previous node of a node
+/// * `current` - The node the path should end at
+fn reconstruct_path<N: Copy + Eq + Hash>(prev_node: &HashMap<N, N>, mut current: N) -> Vec<N> {
+    let mut path = vec![current];
+
+    while let Some(&node) = prev_node.get(&current) {
+        path.insert(0, node);
+        current = node;
+    }
+    path
+}
+
+/// A weighted graph of points in the plane.
+///
+/// Nodes are addressed by their index. Each node knows its `(x, y)`
+/// coordinates, which feed the straight line A* heuristic, and carries an
+/// adjacency list of `(neighbour, weight)` pairs where the weight multiplies
+/// the straight line distance of the edge.
+pub struct Graph {
+    nodes: Vec<(i32, i32)>,
+    adjacency: Vec<Vec<(usize, f64)>>,
+}
+
+// Up to this many waypoints we try every ordering exactly; beyond it the
+// factorial blow-up is too expensive and we fall back to a heuristic tour.
+const EXACT_WAYPOINT_LIMIT: usize = 8;
+
+impl Graph {
+    /// Builds a graph from its node coordinates and adjacency list.
+    pub fn new(nodes: Vec<(i32, i32)>, adjacency: Vec<Vec<(usize, f64)>>) -> Graph {
+        Graph { nodes, adjacency }
+    }
+
+    /// Returns the straight line distance between two nodes.
+    pub fn distance(&self, node1: usize, node2: usize) -> f64 {
+        (((self.nodes[node1].0 - self.nodes[node2].0) as f64).powi(2)
+            + ((self.nodes[node1].1 - self.nodes[node2].1) as f64).powi(2))
+        .sqrt()
+    }
+
+    /// Returns the cheapest path from `start` to `goal` and its length using
+    /// A* with the straight line heuristic, or `None` if `goal` is unreachable.
+    ///
+    /// The result is only guaranteed shortest when every edge weight is `>= 1`.
+    /// Because an edge costs `weight * distance`, a weight below `1` makes the
+    /// straight line heuristic overestimate the remaining cost, breaking A*'s
+    /// admissibility so a suboptimal path can come back. Use
+    /// [`Graph::bellman_ford`] when the weights may be sub-unit (or negative).
+    pub fn shortest_path(&self, start: usize, goal: usize) -> Option<(Vec<usize>, f64)> {
+        self.search(start, goal, None)
+    }
+
+    /// Like [`Graph::shortest_path`] but keeps only the `beam_width` most
+    /// promising frontier entries, bounding memory on huge graphs at the cost
+    /// of an approximate (no longer guaranteed shortest) path.
+    pub fn shortest_path_beam(
+        &self,
+        start: usize,
+        goal: usize,
+        beam_width: usize,
+    ) -> Option<(Vec<usize>, f64)> {
+        self.search(start, goal, Some(beam_width))
+    }
+
+    /// Shared A* driver behind the exact and beam-limited searches.
+    fn search(
+        &self,
+        start: usize,
+        goal: usize,
+        beam_width: Option<usize>,
+    ) -> Option<(Vec<usize>, f64)> {
+        astar(
+            start,
+            |&node| {
+                self.adjacency[node]
+                    .iter()
+                    .map(|&(neighbour, weight)| {
+                        (neighbour, weight * self.distance(node, neighbour))
+                    })
+                    .collect()
+            },
+            |&node| self.distance(node, goal),
+            |&node| node == goal,
+            beam_width,
+        )
+    }
+}
+
+/// Routes from `start` through every node in `waypoints` and on to `goal`
+/// with (close to) minimal total length, choosing the order in which the
+/// waypoints are visited. Returns the concatenated path and its length, or
+/// `None` if some required stop cannot reach another.
+///
+/// The single-pair A* search is used as a primitive to build a table of
+/// pairwise shortest distances between the required stops. The cheapest
+/// visiting order is then found exactly by trying every permutation when
+/// there are few waypoints, and approximately - nearest neighbour followed by
+/// 2-opt improvement - once the permutation count would be too large.
+///
+/// The pairwise legs come from [`Graph::shortest_path`], so (like that search)
+/// the reported tour is only guaranteed minimal when every edge weight is
+/// `>= 1`; with sub-unit weights the legs, and hence the tour, may be
+/// suboptimal.
+///
+/// # Arguments
+///
+/// * `graph` - The graph to route on
+/// * `start` - The node the route starts at
+/// * `waypoints` - The intermediate nodes that must all be visited
+/// * `goal` - The node the route ends at
+pub fn route_through(
+    graph: &Graph,
+    start: usize,
+    waypoints: &[usize],
+    goal: usize,
+) -> Option<(Vec<usize>, f64)> {
+    // Without intermediate stops this is just a single-pair search.
+    if waypoints.is_empty() {
+        return graph.shortest_path(start, goal);
+    }
+
+    // `stops` are the required nodes: start first, goal last, waypoints in
+    // between. The visiting order we search over only permutes the waypoints.
+    let mut stops = Vec::with_capacity(waypoints.len() + 2);
+    stops.push(start);
+    stops.extend_from_slice(waypoints);
+    stops.push(goal);
+    let goal_stop = stops.len() - 1;
+
+    // Precompute the pairwise shortest path and distance between every pair of
+    // required stops. The graph is undirected, so one direction is enough and
+    // the reverse is mirrored.
+    let n = stops.len();
+    let mut dist = vec![vec![0_f64; n]; n];
+    let mut paths = vec![vec![Vec::new(); n]; n];
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let (path, length) = graph.shortest_path(stops[i], stops[j])?;
+            let mut reversed = path.clone();
+            reversed.reverse();
+            dist[i][j] = length;
+            dist[j][i] = length;
+            paths[i][j] = path;
+            paths[j][i] = reversed;
+        }
+    }
+
+    // Find the cheapest order in which to visit the waypoints (stop indices
+    // 1..goal_stop), keeping `start` first and `goal` last.
+    let middle: Vec<usize> = (1..goal_stop).collect();
+    let best_order = if middle.len() <= EXACT_WAYPOINT_LIMIT {
+        best_order_exact(&middle, goal_stop, &dist)
+    } else {
+        best_order_heuristic(&middle, goal_stop, &dist)
+    };
+
+    // Stitch the per-leg shortest paths into one path, dropping the shared
+    // joint node between consecutive legs, and sum up the total length.
+    let mut full_path = paths[0][best_order[0]].clone();
+    let mut length = dist[0][best_order[0]];
+    for window in best_order.windows(2) {
+        let (from, to) = (window[0], window[1]);
+        full_path.extend_from_slice(&paths[from][to][1..]);
+        length += dist[from][to];
+    }
+
+    Some((full_path, length))
+}
+
+/// Cost of a full tour `0 -> order -> ...` expressed as stop indices.
+fn tour_cost(order: &[usize], dist: &[Vec<f64>]) -> f64 {
+    let mut cost = dist[0][order[0]];
+    for window in order.windows(2) {
+        cost += dist[window[0]][window[1]];
+    }
+    cost
+}
+
+/// Exhaustively scores every permutation of the waypoints and returns the
+/// cheapest ordering, including the fixed `goal` stop at the end.
+fn best_order_exact(middle: &[usize], goal_stop: usize, dist: &[Vec<f64>]) -> Vec<usize> {
+    let mut best: Option<(f64, Vec<usize>)> = None;
+    for mut order in permutations(middle) {
+        order.push(goal_stop);
+        let cost = tour_cost(&order, dist);
+        if best.as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+            best = Some((cost, order));
+        }
+    }
+    best.expect("there is always at least one permutation").1
+}
+
+/// Builds a waypoint ordering with nearest neighbour and then improves it with
+/// 2-opt, returning the ordering with the fixed `goal` stop appended.
+fn best_order_heuristic(middle: &[usize], goal_stop: usize, dist: &[Vec<f64>]) -> Vec<usize> {
+    // Nearest neighbour: repeatedly hop to the closest unvisited waypoint,
+    // starting from the `start` stop (index 0).
+    let mut remaining: Vec<usize> = middle.to_vec();
+    let mut order: Vec<usize> = Vec::with_capacity(middle.len() + 1);
+    let mut current = 0;
+    while !remaining.is_empty() {
+        let (pos, &next) = remaining
+            .iter()
+            .enumerate()
+            .min_by(|(_, &a), (_, &b)| dist[current][a].total_cmp(&dist[current][b]))
+            .unwrap();
+        order.push(next);
+        current = next;
+        remaining.swap_remove(pos);
+    }
+    order.push(goal_stop);
+
+    // 2-opt: keep reversing a subsegment of the waypoint part whenever doing
+    // so shortens the tour, until no reversal helps. The fixed `start` stop
+    // (index 0) and the fixed `goal` stop (last) are never moved.
+    //
+    // Reversing order[i..=j] only changes the two edges at its boundaries
+    // (everything inside the segment keeps the same neighbours, just in
+    // reverse), so score a candidate reversal by that edge delta instead of
+    // recomputing the whole tour - O(1) per candidate instead of O(m).
+    let last = order.len() - 1;
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 0..last {
+            let prev = if i == 0 { 0 } else { order[i - 1] };
+            for j in (i + 1)..last {
+                let next = order[j + 1];
+                let before = dist[prev][order[i]] + dist[order[j]][next];
+                let after = dist[prev][order[j]] + dist[order[i]][next];
+                if after + f64::EPSILON < before {
+                    order[i..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+    }
+
+    order
+}
+
+/// Returns every permutation of `items` using Heap's algorithm.
+fn permutations(items: &[usize]) -> Vec<Vec<usize>> {
+    let mut current = items.to_vec();
+    let mut result = Vec::new();
+    let n = current.len();
+    let mut counters = vec![0_usize; n];
+
+    result.push(current.clone());
+    let mut i = 0;
+    while i < n {
+        if counters[i] < i {
+            if i % 2 == 0 {
+                current.swap(0, i);
+            } else {
+                current.swap(counters[i], i);
+            }
+            result.push(current.clone());
+            counters[i] += 1;
+            i = 0;
+        } else {
+            counters[i] = 0;
+            i += 1;
+        }
+    }
+    result
+}
+
+/// Returned by [`Graph::bellman_ford`] when the graph contains a negative
+/// weight cycle reachable from the start, which leaves shortest paths
+/// undefined because a tour of the cycle keeps lowering the total cost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NegativeCycle;
+
+impl Graph {
+    /// Returns the cheapest path from `start` to `goal` and its length using a
+    /// Bellman-Ford relaxation, or `None` if `goal` is unreachable.
+    ///
+    /// Unlike [`Graph::shortest_path`], this makes no assumption that edge
+    /// costs agree with the straight line heuristic, so it stays correct when
+    /// the matrix weights are non-geometric or even negative. The queue is
+    /// driven by the Small-Label-First / Large-Label-Last heuristics: a node
+    /// whose tentative distance was lowered is pushed to the *front* when it is
+    /// smaller than the current front and to the *back* otherwise (SLF), and
+    /// before popping, a front node whose distance exceeds the average of the
+    /// queued nodes is rotated to the back (LLL). If any node is relaxed more
+    /// than `V` times the graph has a negative cycle and [`NegativeCycle`] is
+    /// returned.
+    pub fn bellman_ford(
+        &self,
+        start: usize,
+        goal: usize,
+    ) -> Result<Option<(Vec<usize>, f64)>, NegativeCycle> {
+        let num_nodes = self.nodes.len();
+
+        // Tentative distance from `start` to each node and the predecessor on
+        // the best path found so far, reused for path reconstruction.
+        let mut dist: HashMap<usize, f64> = HashMap::new();
+        dist.insert(start, 0_f64);
+        let mut prev_node: HashMap<usize, usize> = HashMap::new();
+
+        // Nodes whose outgoing edges still need relaxing. `in_queue` keeps the
+        // deque free of duplicates; `relaxations` counts updates per node so a
+        // negative cycle can be detected.
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        queue.push_back(start);
+        let mut in_queue = vec![false; num_nodes];
+        in_queue[start] = true;
+        let mut relaxations = vec![0_usize; num_nodes];
+
+        while !queue.is_empty() {
+            // LLL: rotate any front node whose distance is above the queue
+            // average to the back. We cap the rotations at one full pass so
+            // that floating-point rounding of the average (which can land just
+            // below every queued distance) can never spin the loop forever.
+            let average =
+                queue.iter().map(|&node| dist[&node]).sum::<f64>() / queue.len() as f64;
+            for _ in 0..queue.len() {
+                if dist[queue.front().unwrap()] <= average {
+                    break;
+                }
+                queue.rotate_left(1);
+            }
+
+            let current = queue.pop_front().unwrap();
+            in_queue[current] = false;
+            let current_dist = dist[&current];
+
+            for &(neighbour, weight) in &self.adjacency[current] {
+                let tentative = current_dist + weight * self.distance(current, neighbour);
+
+                if dist.get(&neighbour).is_none_or(|&best| tentative < best) {
+                    dist.insert(neighbour, tentative);
+                    prev_node.insert(neighbour, current);
+
+                    relaxations[neighbour] += 1;
+                    if relaxations[neighbour] > num_nodes {
+                        return Err(NegativeCycle);
+                    }
+
+                    if !in_queue[neighbour] {
+                        // SLF: jump the queue when this label beats the front.
+                        match queue.front() {
+                            Some(&front) if tentative < dist[&front] => {
+                                queue.push_front(neighbour)
+                            }
+                            _ => queue.push_back(neighbour),
+                        }
+                        in_queue[neighbour] = true;
+                    }
+                }
+            }
+        }
+
+        Ok(dist
+            .get(&goal)
+            .map(|&length| (reconstruct_path(&prev_node, goal), length)))
+    }
+}
+
+impl Graph {
+    /// Hashes the graph (coordinates and weighted adjacency list) into a single
+    /// `u64`. A [`PrecomputedTable`] stores this value so a cached table can be
+    /// rejected when it was built for a different map.
+    pub fn graph_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.nodes.hash(&mut hasher);
+        for list in &self.adjacency {
+            (list.len() as u64).hash(&mut hasher);
+            for &(neighbour, weight) in list {
+                neighbour.hash(&mut hasher);
+                // f64 is not `Hash`, so fold in its raw bit pattern instead.
+                weight.to_bits().hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Runs a full single-source expansion from `start` - Dijkstra, i.e. A*
+    /// with a zero heuristic - and records the travelled cost and predecessor
+    /// of every reachable node in a [`PrecomputedTable`]. The table can be
+    /// saved once and then queried for many goals without redoing the search.
+    pub fn precompute(&self, start: usize) -> PrecomputedTable {
+        let num_nodes = self.nodes.len();
+        let mut cost = vec![f64::INFINITY; num_nodes];
+        let mut prev = vec![usize::MAX; num_nodes];
+
+        let mut frontier = BinaryHeap::new();
+        cost[start] = 0_f64;
+        frontier.push(NodeCost {
+            cost: 0_f64,
+            node: start,
+        });
+
+        while let Some(current) = frontier.pop() {
+            // Lazily skip stale entries, as in `astar`.
+            if current.cost > cost[current.node] {
+                continue;
+            }
+
+            for &(neighbour, weight) in &self.adjacency[current.node] {
+                let tentative = cost[current.node] + weight * self.distance(current.node, neighbour);
+                if tentative < cost[neighbour] {
+                    cost[neighbour] = tentative;
+                    prev[neighbour] = current.node;
+                    frontier.push(NodeCost {
+                        cost: tentative,
+                        node: neighbour,
+                    });
+                }
+            }
+        }
+
+        PrecomputedTable {
+            start,
+            graph_hash: self.graph_hash(),
+            cost,
+            prev,
+        }
+    }
+}
+
+/// A precomputed single-source distance and predecessor table.
+///
+/// Built with [`Graph::precompute`], it holds the travelled cost and the
+/// previous node on the best path for every node reachable from the source.
+/// Once serialized with [`PrecomputedTable::save`] it can be reloaded with
+/// [`PrecomputedTable::load`] and, as long as [`PrecomputedTable::is_valid_for`]
+/// confirms the graph is unchanged, any goal can be answered in O(path length)
+/// with [`PrecomputedTable::query`] - no search required.
+pub struct PrecomputedTable {
+    /// The source node the expansion started from
+    start: usize,
+    /// Hash of the graph the table was built for
+    graph_hash: u64,
+    /// Travelled cost to each node, `f64::INFINITY` when unreachable
+    cost: Vec<f64>,
+    /// Previous node on the best path, `usize::MAX` when there is none
+    prev: Vec<usize>,
+}
+
+impl PrecomputedTable {
+    /// Returns `true` when this table was built for `graph`, i.e. the stored
+    /// graph hash matches. Use it to decide whether a loaded cache can be
+    /// trusted or the search has to be redone.
+    pub fn is_valid_for(&self, graph: &Graph) -> bool {
+        self.graph_hash == graph.graph_hash()
+    }
+
+    /// Returns the path from the source to `goal` and its length by walking the
+    /// predecessor table, or `None` if `goal` was not reachable.
+    pub fn query(&self, goal: usize) -> Option<(Vec<usize>, f64)> {
+        if goal >= self.cost.len() || !self.cost[goal].is_finite() {
+            return None;
+        }
+
+        let mut path = vec![goal];
+        let mut current = goal;
+        while current != self.start {
+            current = self.prev[current];
+            path.push(current);
+        }
+        path.reverse();
+
+        Some((path, self.cost[goal]))
+    }
+
+    /// Serializes the table to `path` in a compact little-endian binary format.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(24 + self.cost.len() * 16);
+        buf.extend_from_slice(&(self.start as u64).to_le_bytes());
+        buf.extend_from_slice(&self.graph_hash.to_le_bytes());
+        buf.extend_from_slice(&(self.cost.len() as u64).to_le_bytes());
+        for &cost in &self.cost {
+            buf.extend_from_slice(&cost.to_bits().to_le_bytes());
+        }
+        for &prev in &self.prev {
+            buf.extend_from_slice(&(prev as u64).to_le_bytes());
+        }
+        fs::write(path, buf)
+    }
+
+    /// Loads a table previously written by [`PrecomputedTable::save`].
+    pub fn load(path: &str) -> io::Result<PrecomputedTable> {
+        let bytes = fs::read(path)?;
+        let mut at = 0;
+
+        let start = take_u64(&bytes, &mut at)? as usize;
+        let graph_hash = take_u64(&bytes, &mut at)?;
+        let len = take_u64(&bytes, &mut at)? as usize;
+
+        // Reject a bogus length before trusting it to size allocations: the
+        // file must hold exactly the header plus one cost and one predecessor
+        // word per node.
+        if len.checked_mul(16).and_then(|payload| payload.checked_add(at)) != Some(bytes.len()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "cache file is corrupt"));
+        }
+
+        let mut cost = Vec::with_capacity(len);
+        for _ in 0..len {
+            cost.push(f64::from_bits(take_u64(&bytes, &mut at)?));
+        }
+        let mut prev = Vec::with_capacity(len);
+        for _ in 0..len {
+            prev.push(take_u64(&bytes, &mut at)? as usize);
+        }
+
+        Ok(PrecomputedTable {
+            start,
+            graph_hash,
+            cost,
+            prev,
+        })
+    }
+}
+
+/// Reads a little-endian `u64` at `*at` and advances the cursor, erroring out
+/// when the buffer is too short.
+fn take_u64(bytes: &[u8], at: &mut usize) -> io::Result<u64> {
+    let end = *at + 8;
+    let slice = bytes
+        .get(*at..end)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "cache file is truncated"))?;
+    *at = end;
+    Ok(u64::from_le_bytes(slice.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    // A right triangle with unit weights: edge cost equals straight line
+    // distance, so the A* heuristic is admissible and agrees with Bellman-Ford.
+    fn triangle() -> Graph {
+        let nodes = vec![(0, 0), (3, 0), (0, 4)];
+        let adjacency = vec![
+            vec![(1, 1.0), (2, 1.0)],
+            vec![(0, 1.0), (2, 1.0)],
+            vec![(0, 1.0), (1, 1.0)],
+        ];
+        Graph::new(nodes, adjacency)
+    }
+
+    #[test]
+    fn astar_and_bellman_ford_agree() {
+        let graph = triangle();
+        let (_, astar_len) = graph.shortest_path(0, 2).unwrap();
+        let (_, bf_len) = graph.bellman_ford(0, 2).unwrap().unwrap();
+        // The direct 0 -> 2 edge (length 4) beats the 0 -> 1 -> 2 detour (8).
+        assert!((astar_len - 4.0).abs() < 1e-9);
+        assert!((astar_len - bf_len).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bellman_ford_detects_negative_cycle() {
+        // A single negative undirected edge is a negative cycle on its own:
+        // hopping back and forth across it lowers the distance without bound.
+        let nodes = vec![(0, 0), (1, 0)];
+        let adjacency = vec![vec![(1, -1.0)], vec![(0, -1.0)]];
+        let graph = Graph::new(nodes, adjacency);
+        assert_eq!(graph.bellman_ford(0, 1), Err(NegativeCycle));
+    }
+
+    // A chain 0 - 1 - 2 - ... - (n - 1) with unit-weight edges between
+    // consecutive nodes only, so reaching any node requires hopping through
+    // every node in between - a sparse, multi-hop graph.
+    fn line_graph(n: usize) -> Graph {
+        let nodes = (0..n as i32).map(|i| (i, 0)).collect();
+        let adjacency = (0..n)
+            .map(|i| {
+                let mut links = Vec::new();
+                if i > 0 {
+                    links.push((i - 1, 1.0));
+                }
+                if i + 1 < n {
+                    links.push((i + 1, 1.0));
+                }
+                links
+            })
+            .collect();
+        Graph::new(nodes, adjacency)
+    }
+
+    // A graph with two routes from 0 to the goal (3): a direct one through 1
+    // that is cheapest overall, and a detour through 2 and 4 that looks
+    // cheapest at the first expansion because 2 sits almost on top of the
+    // goal in a straight line, even though the only other edge out of 2 doubles
+    // back through a far-away node (4) before reaching it.
+    fn branchy_graph() -> Graph {
+        let nodes = vec![(6, 0), (6, 8), (0, 1), (0, 0), (0, 100)];
+        let adjacency = vec![
+            vec![(1, 1.0), (2, 1.0)],
+            vec![(0, 1.0), (3, 1.0)],
+            vec![(0, 1.0), (4, 1.0)],
+            vec![(1, 1.0), (4, 1.0)],
+            vec![(2, 1.0), (3, 1.0)],
+        ];
+        Graph::new(nodes, adjacency)
+    }
+
+    #[test]
+    fn beam_width_one_follows_the_locally_cheaper_branch() {
+        let graph = branchy_graph();
+
+        let (exact_path, exact_len) = graph.shortest_path(0, 3).unwrap();
+        assert_eq!(exact_path, vec![0, 1, 3]);
+        let expected_exact = graph.distance(0, 1) + graph.distance(1, 3);
+        assert!((exact_len - expected_exact).abs() < 1e-9);
+
+        // With only one frontier slot kept after expanding the start node, the
+        // branch through 1 looks worse (higher cost-so-far + heuristic) than
+        // the branch through 2, so 1 is discarded and the search is forced
+        // down the far longer detour through 2 and 4.
+        let (beam_path, beam_len) = graph.shortest_path_beam(0, 3, 1).unwrap();
+        assert_eq!(beam_path, vec![0, 2, 4, 3]);
+        let expected_beam = graph.distance(0, 2) + graph.distance(2, 4) + graph.distance(4, 3);
+        assert!((beam_len - expected_beam).abs() < 1e-9);
+
+        assert!(beam_len > exact_len);
+    }
+
+    #[test]
+    fn route_through_orders_waypoints_exactly() {
+        let graph = line_graph(5);
+        // Waypoints given out of visiting order; the only sane tour still
+        // walks the chain straight through in ascending order.
+        let (path, length) = route_through(&graph, 0, &[3, 1], 4).unwrap();
+
+        assert_eq!(path, vec![0, 1, 2, 3, 4]);
+        assert!((length - 4.0).abs() < 1e-9);
+        for &waypoint in &[1, 3] {
+            assert!(path.contains(&waypoint));
+        }
+    }
+
+    #[test]
+    fn route_through_orders_waypoints_heuristically() {
+        let goal = 13;
+        let graph = line_graph(goal + 1);
+        // More waypoints than EXACT_WAYPOINT_LIMIT, given in reverse, so this
+        // exercises best_order_heuristic rather than the exact permutation
+        // search.
+        let waypoints: Vec<usize> = (1..goal).rev().collect();
+        assert!(waypoints.len() > EXACT_WAYPOINT_LIMIT);
+
+        let (path, length) = route_through(&graph, 0, &waypoints, goal).unwrap();
+
+        assert_eq!(path, (0..=goal).collect::<Vec<usize>>());
+        assert!((length - goal as f64).abs() < 1e-9);
+        for waypoint in waypoints {
+            assert!(path.contains(&waypoint));
+        }
+    }
+
+    #[test]
+    fn precomputed_table_survives_save_and_load() {
+        let graph = triangle();
+        let table = graph.precompute(0);
+        let path = env::temp_dir().join("rastar_test_table.cache");
+        let path = path.to_str().unwrap();
+
+        table.save(path).unwrap();
+        let loaded = PrecomputedTable::load(path).unwrap();
+
+        assert!(loaded.is_valid_for(&graph));
+        assert_eq!(table.query(2), loaded.query(2));
+        assert_eq!(loaded.query(2).unwrap().1, 4.0);
+        fs::remove_file(path).unwrap();
+    }
+}